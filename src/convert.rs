@@ -0,0 +1,216 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use anyhow::{Error, Result};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+/// Half-width (in taps, each side of the center) of the windowed-sinc kernel
+/// used by `resample`.
+const RESAMPLE_HALF_WIDTH: isize = 8;
+
+/// A WAV file decoded into deinterleaved, normalized `[-1.0, 1.0]` f32 samples,
+/// one `Vec` per channel, alongside the original spec needed to write it back
+/// out in its original format.
+pub struct Audio {
+    pub spec: WavSpec,
+    pub channels: Vec<Vec<f32>>,
+}
+
+/// Decodes any WAV file hound can open (8/16/24/32-bit integer or 32-bit float
+/// PCM, any channel count, any sample rate) into deinterleaved f32 channels.
+pub fn load(path: &Path) -> Result<Audio> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let num_channels = spec.channels as usize;
+    let mut channels: Vec<Vec<f32>> = vec![Vec::new(); num_channels];
+
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for (i, sample) in reader.samples::<f32>().enumerate() {
+                channels[i % num_channels].push(sample?);
+            }
+        }
+        SampleFormat::Int => {
+            let scale = 2f32.powi(spec.bits_per_sample as i32 - 1);
+            for (i, sample) in reader.samples::<i32>().enumerate() {
+                channels[i % num_channels].push(sample? as f32 / scale);
+            }
+        }
+    }
+
+    Ok(Audio { spec, channels })
+}
+
+/// Encodes deinterleaved f32 channels back to a WAV file using `spec`'s
+/// original bit depth and sample format. `spec.channels` must match
+/// `channels.len()`.
+pub fn store(path: &Path, channels: &[Vec<f32>], spec: WavSpec) -> Result<()> {
+    let mut writer = WavWriter::create(path, spec)?;
+    let num_frames = channels.iter().map(Vec::len).max().unwrap_or(0);
+
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for frame in 0..num_frames {
+                for channel in channels {
+                    writer.write_sample(channel.get(frame).copied().unwrap_or(0.0))?;
+                }
+            }
+        }
+        SampleFormat::Int => {
+            let scale = 2f32.powi(spec.bits_per_sample as i32 - 1);
+            let min = -scale;
+            let max = scale - 1.0;
+            for frame in 0..num_frames {
+                for channel in channels {
+                    let sample = channel.get(frame).copied().unwrap_or(0.0);
+                    let quantized = (sample * scale).clamp(min, max) as i32;
+                    writer.write_sample(quantized)?;
+                }
+            }
+        }
+    }
+
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Reconciles a decoded channel layout with a requested output channel count,
+/// either downmixing to mono or upmixing a mono source by duplication.
+pub fn remix(channels: Vec<Vec<f32>>, target: usize) -> Result<Vec<Vec<f32>>> {
+    if channels.len() == target {
+        return Ok(channels);
+    }
+    if target == 1 {
+        return Ok(vec![downmix_to_mono(&channels)]);
+    }
+    if channels.len() == 1 {
+        return Ok(upmix_from_mono(&channels[0], target));
+    }
+    Err(Error::msg(format!(
+        "Cannot remix {} channel(s) to {}: only downmix-to-mono and upmix-from-mono are supported",
+        channels.len(),
+        target
+    )))
+}
+
+/// Averages all channels down to a single mono channel.
+pub fn downmix_to_mono(channels: &[Vec<f32>]) -> Vec<f32> {
+    if channels.len() == 1 {
+        return channels[0].clone();
+    }
+    let num_frames = channels.iter().map(Vec::len).max().unwrap_or(0);
+    let num_channels = channels.len().max(1) as f32;
+    (0..num_frames)
+        .map(|frame| {
+            channels
+                .iter()
+                .map(|c| c.get(frame).copied().unwrap_or(0.0))
+                .sum::<f32>()
+                / num_channels
+        })
+        .collect()
+}
+
+/// Duplicates a mono channel out to `num_channels` identical channels.
+pub fn upmix_from_mono(mono: &[f32], num_channels: usize) -> Vec<Vec<f32>> {
+    vec![mono.to_vec(); num_channels]
+}
+
+/// Resamples `input` from `from_hz` to `to_hz` using windowed-sinc
+/// interpolation, falling back to linear interpolation near the edges of the
+/// buffer where the full sinc kernel would run off the end.
+pub fn resample(input: &[f32], from_hz: f32, to_hz: f32) -> Vec<f32> {
+    if input.is_empty() || (from_hz - to_hz).abs() < f32::EPSILON {
+        return input.to_vec();
+    }
+
+    let ratio = to_hz / from_hz;
+    let out_len = ((input.len() as f32) * ratio).round().max(0.0) as usize;
+
+    (0..out_len)
+        .map(|i| sinc_resample_sample(input, i as f32 / ratio))
+        .collect()
+}
+
+fn sinc_resample_sample(input: &[f32], pos: f32) -> f32 {
+    let len = input.len() as isize;
+    let base = pos.floor() as isize;
+    let frac = pos - pos.floor();
+
+    if base < RESAMPLE_HALF_WIDTH || len - base <= RESAMPLE_HALF_WIDTH {
+        let i0 = base.clamp(0, len - 1) as usize;
+        let i1 = (base + 1).clamp(0, len - 1) as usize;
+        return input[i0] * (1.0 - frac) + input[i1] * frac;
+    }
+
+    (-RESAMPLE_HALF_WIDTH..RESAMPLE_HALF_WIDTH)
+        .map(|tap| {
+            let x = tap as f32 - frac;
+            let window = 0.5 + 0.5 * (PI * x / RESAMPLE_HALF_WIDTH as f32).cos();
+            input[(base + tap) as usize] * sinc(x) * window
+        })
+        .sum()
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downmix_averages_channels() {
+        let channels = vec![vec![1.0, 1.0], vec![-1.0, 0.0]];
+        assert_eq!(downmix_to_mono(&channels), vec![0.0, 0.5]);
+    }
+
+    #[test]
+    fn upmix_duplicates_mono() {
+        let mono = vec![0.1, 0.2, 0.3];
+        let channels = upmix_from_mono(&mono, 2);
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0], mono);
+        assert_eq!(channels[1], mono);
+    }
+
+    #[test]
+    fn remix_is_a_no_op_when_counts_match() {
+        let channels = vec![vec![0.5], vec![0.25]];
+        let remixed = remix(channels.clone(), 2).unwrap();
+        assert_eq!(remixed, channels);
+    }
+
+    #[test]
+    fn remix_rejects_unsupported_multichannel_conversions() {
+        let channels = vec![vec![0.0], vec![0.0], vec![0.0]];
+        assert!(remix(channels, 2).is_err());
+    }
+
+    #[test]
+    fn resample_is_a_no_op_at_equal_rates() {
+        let input = vec![0.1, -0.2, 0.3];
+        assert_eq!(resample(&input, 16000.0, 16000.0), input);
+    }
+
+    #[test]
+    fn resample_scales_output_length_by_the_rate_ratio() {
+        let input = vec![0.0f32; 1000];
+        let output = resample(&input, 16000.0, 8000.0);
+        assert_eq!(output.len(), 500);
+    }
+
+    #[test]
+    fn resample_reproduces_a_dc_signal() {
+        let input = vec![0.5f32; 200];
+        let output = resample(&input, 16000.0, 22050.0);
+        for (i, &s) in output.iter().enumerate() {
+            assert!((s - 0.5).abs() < 1e-3, "sample {i}: {s}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use rustfft::FftPlanner;
+use rustfft::num_complex::Complex32;
+
+/// Block size (in samples) for the partitioned overlap-add convolution. Each
+/// partition's FFT operates on `2 * BLOCK_LEN` samples (zero-padded).
+const BLOCK_LEN: usize = 512;
+
+/// A single impulse-response partition, pre-transformed into the frequency domain.
+struct Partition {
+    spectrum: Vec<Complex32>,
+}
+
+/// Convolves `input` with the impulse response `ir` using partitioned overlap-add
+/// FFT convolution, mixing `wet` parts of the reverberated signal with `1 - wet`
+/// parts dry. The impulse response is normalized to unit peak before use.
+///
+/// The returned buffer is longer than `input` by roughly `ir.len()` samples, so
+/// the reverb tail isn't truncated once the dry input ends.
+pub fn convolve(input: &[f32], ir: &[f32], wet: f32) -> Vec<f32> {
+    let ir = normalize(ir);
+    let fft_len = BLOCK_LEN * 2;
+    let num_partitions = ir.len().div_ceil(BLOCK_LEN).max(1);
+
+    let mut planner = FftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(fft_len);
+    let ifft = planner.plan_fft_inverse(fft_len);
+
+    let partitions: Vec<Partition> = (0..num_partitions)
+        .map(|k| Partition {
+            spectrum: forward_fft(&fft, &ir, k * BLOCK_LEN, fft_len),
+        })
+        .collect();
+
+    // A few extra blocks of silence after the input ends so the comb of
+    // partition delays finishes flushing the IR's tail into the output.
+    let num_blocks = input.len().div_ceil(BLOCK_LEN) + num_partitions;
+
+    let mut history: Vec<Vec<Complex32>> =
+        vec![vec![Complex32::new(0.0, 0.0); fft_len]; num_partitions];
+    let mut history_pos = 0usize;
+    let mut overlap_tail = vec![0.0f32; BLOCK_LEN];
+    let mut wet_signal = vec![0.0f32; num_blocks * BLOCK_LEN];
+
+    for block_idx in 0..num_blocks {
+        let start = block_idx * BLOCK_LEN;
+        history[history_pos] = forward_fft(&fft, input, start, fft_len);
+
+        let mut acc = vec![Complex32::new(0.0, 0.0); fft_len];
+        for (k, partition) in partitions.iter().enumerate() {
+            let hist_idx = (history_pos + num_partitions - k) % num_partitions;
+            for (a, (h, p)) in acc
+                .iter_mut()
+                .zip(history[hist_idx].iter().zip(partition.spectrum.iter()))
+            {
+                *a += h * p;
+            }
+        }
+        ifft.process(&mut acc);
+
+        let norm = 1.0 / fft_len as f32;
+        for i in 0..BLOCK_LEN {
+            wet_signal[start + i] = acc[i].re * norm + overlap_tail[i];
+            overlap_tail[i] = acc[BLOCK_LEN + i].re * norm;
+        }
+
+        history_pos = (history_pos + 1) % num_partitions;
+    }
+
+    let dry = 1.0 - wet;
+    wet_signal
+        .iter()
+        .enumerate()
+        .map(|(i, &w)| dry * input.get(i).copied().unwrap_or(0.0) + wet * w)
+        .collect()
+}
+
+/// Zero-pads `BLOCK_LEN` samples of `source` starting at `offset` to `fft_len`
+/// and runs the forward FFT in place.
+fn forward_fft(
+    fft: &std::sync::Arc<dyn rustfft::Fft<f32>>,
+    source: &[f32],
+    offset: usize,
+    fft_len: usize,
+) -> Vec<Complex32> {
+    let mut buf = vec![Complex32::new(0.0, 0.0); fft_len];
+    let end = (offset + BLOCK_LEN).min(source.len());
+    if offset < end {
+        for (i, &s) in source[offset..end].iter().enumerate() {
+            buf[i] = Complex32::new(s, 0.0);
+        }
+    }
+    fft.process(&mut buf);
+    buf
+}
+
+/// Normalizes `ir` so its peak absolute sample is 1.0, leaving silence untouched.
+fn normalize(ir: &[f32]) -> Vec<f32> {
+    let peak = ir.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+    if peak > 0.0 {
+        ir.iter().map(|&s| s / peak).collect()
+    } else {
+        ir.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_impulse_response_is_passthrough() {
+        let input = vec![0.1, -0.2, 0.3, -0.4, 0.5];
+        let ir = vec![1.0];
+        let output = convolve(&input, &ir, 1.0);
+        for (i, &s) in input.iter().enumerate() {
+            assert!(
+                (output[i] - s).abs() < 1e-4,
+                "sample {i}: {} vs {}",
+                output[i],
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn dry_wet_mix_at_zero_wet_is_untouched_input() {
+        let input = vec![0.5; 8];
+        let ir = vec![1.0, 0.5, 0.25];
+        let output = convolve(&input, &ir, 0.0);
+        for (i, &s) in input.iter().enumerate() {
+            assert!(
+                (output[i] - s).abs() < 1e-4,
+                "sample {i}: {} vs {}",
+                output[i],
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn output_is_longer_than_input_to_hold_the_tail() {
+        let input = vec![1.0; 10];
+        let ir = vec![1.0; 1000];
+        let output = convolve(&input, &ir, 1.0);
+        assert!(output.len() > input.len());
+    }
+}
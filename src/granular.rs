@@ -0,0 +1,217 @@
+//! Granular effects: `freeze` (sustain a captured grain indefinitely) and
+//! `granular` (a jittered cloud of overlapping grains), both built on
+//! Hann-windowed overlap-add.
+
+use rand::Rng;
+
+use crate::interpolation::{InterpolationMode, interpolate};
+
+/// Captures a `grain_ms` window of `input` starting at `freeze_position_ms`
+/// and loops it, crossfaded via Hann-windowed overlap-add, for the remainder
+/// of the signal. Audio before the freeze point passes through unchanged.
+pub fn apply_freeze(
+    input: &[f32],
+    sr: f32,
+    wet: f32,
+    freeze_position_ms: f32,
+    grain_ms: f32,
+) -> Vec<f32> {
+    let freeze_start = ((freeze_position_ms * sr / 1000.0) as usize).min(input.len());
+    let sustain_len = input.len() - freeze_start;
+
+    if sustain_len == 0 {
+        return input.to_vec();
+    }
+
+    let grain_len = ((grain_ms * sr / 1000.0).max(2.0) as usize)
+        .min(sustain_len)
+        .max(1);
+    let grain = &input[freeze_start..freeze_start + grain_len];
+    let hop = (grain_len / 2).max(1);
+
+    let mut wet_tail = vec![0.0f32; sustain_len];
+    let mut envelope_sum = vec![0.0f32; sustain_len];
+
+    let mut pos = 0usize;
+    while pos < sustain_len {
+        for (i, &s) in grain.iter().enumerate() {
+            let idx = pos + i;
+            if idx >= sustain_len {
+                break;
+            }
+            let w = hann_window(i, grain_len);
+            wet_tail[idx] += s * w;
+            envelope_sum[idx] += w;
+        }
+        pos += hop;
+    }
+
+    let tail = normalize_overlap_add(&wet_tail, &envelope_sum, &input[freeze_start..]);
+    let dry = 1.0 - wet;
+
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, &inp)| {
+            if i < freeze_start {
+                inp
+            } else {
+                dry * inp + wet * tail[i - freeze_start]
+            }
+        })
+        .collect()
+}
+
+/// Schedules overlapping Hann-windowed grains across `input`, each optionally
+/// pitch-shifted (via `interpolation::interpolate`, the same fractional-delay
+/// helper used by chorus) and jittered in read position and pitch, for a
+/// grain-cloud texture. Active grains are overlap-added and normalized by a
+/// running sum of their envelopes to avoid amplitude buildup.
+#[allow(clippy::too_many_arguments)]
+pub fn apply_granular(
+    input: &[f32],
+    sr: f32,
+    wet: f32,
+    grain_ms: f32,
+    density_hz: f32,
+    pitch: f32,
+    position_jitter_ms: f32,
+    pitch_jitter: f32,
+    interpolation: InterpolationMode,
+) -> Vec<f32> {
+    if input.is_empty() {
+        return Vec::new();
+    }
+
+    let grain_len = (grain_ms * sr / 1000.0).max(2.0) as usize;
+    let hop = ((sr / density_hz.max(0.1)).max(1.0)) as usize;
+
+    let mut wet_signal = vec![0.0f32; input.len()];
+    let mut envelope_sum = vec![0.0f32; input.len()];
+    let mut rng = rand::thread_rng();
+
+    let mut onset = 0usize;
+    while onset < input.len() {
+        let position_jitter_samples =
+            rng.gen_range(-1.0f32..=1.0) * position_jitter_ms * sr / 1000.0;
+        let read_base = onset as f32 + position_jitter_samples;
+        let grain_pitch = (pitch * (1.0 + rng.gen_range(-1.0f32..=1.0) * pitch_jitter)).max(0.01);
+
+        for i in 0..grain_len {
+            let out_idx = onset + i;
+            if out_idx >= input.len() {
+                break;
+            }
+            let read_pos = read_base + i as f32 * grain_pitch;
+            if read_pos < 0.0 || read_pos > (input.len() - 1) as f32 {
+                // Out of range: `interpolate` treats `input` as circular and
+                // would wrap, splicing in unrelated audio from the other end
+                // of the buffer. Leave this tap out of the overlap-add so the
+                // grain fades via the envelope instead of wrapping.
+                continue;
+            }
+            let sample = interpolate(input, read_pos, interpolation);
+            let w = hann_window(i, grain_len);
+            wet_signal[out_idx] += sample * w;
+            envelope_sum[out_idx] += w;
+        }
+
+        onset += hop.max(1);
+    }
+
+    let cloud = normalize_overlap_add(&wet_signal, &envelope_sum, input);
+    let dry = 1.0 - wet;
+
+    input
+        .iter()
+        .zip(cloud)
+        .map(|(&inp, c)| dry * inp + wet * c)
+        .collect()
+}
+
+/// Hann window value for tap `n` of a window of length `len`.
+fn hann_window(n: usize, len: usize) -> f32 {
+    if len <= 1 {
+        return 1.0;
+    }
+    0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / (len as f32 - 1.0)).cos()
+}
+
+/// Divides an overlap-add signal by its running envelope sum at each sample,
+/// so overlapping grain windows don't build up amplitude. Samples not covered
+/// by any grain (where the Hann window tapers to zero at a grain's edge, in
+/// gaps when the hop is longer than a grain, or where a grain's read position
+/// fell outside the input) fall back to `dry`.
+fn normalize_overlap_add(signal: &[f32], envelope_sum: &[f32], dry: &[f32]) -> Vec<f32> {
+    signal
+        .iter()
+        .zip(envelope_sum)
+        .zip(dry)
+        .map(|((&s, &e), &d)| if e > 1e-6 { s / e } else { d })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freeze_passes_audio_through_before_the_freeze_point() {
+        let input: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.001).sin()).collect();
+        let output = apply_freeze(&input, 16000.0, 1.0, 10.0, 20.0);
+        let freeze_start = (10.0 * 16000.0 / 1000.0) as usize;
+        assert_eq!(&output[..freeze_start], &input[..freeze_start]);
+    }
+
+    #[test]
+    fn freeze_output_matches_input_length() {
+        let input = vec![0.2f32; 5000];
+        let output = apply_freeze(&input, 16000.0, 1.0, 50.0, 30.0);
+        assert_eq!(output.len(), input.len());
+    }
+
+    #[test]
+    fn freeze_sustains_a_constant_signal_without_amplitude_buildup() {
+        let input = vec![0.4f32; 5000];
+        let output = apply_freeze(&input, 16000.0, 1.0, 50.0, 30.0);
+        for (i, &s) in output.iter().enumerate() {
+            assert!((s - 0.4).abs() < 1e-3, "sample {i}: {s}");
+        }
+    }
+
+    #[test]
+    fn granular_output_matches_input_length() {
+        let input = vec![0.3f32; 4000];
+        let output = apply_granular(
+            &input,
+            16000.0,
+            1.0,
+            30.0,
+            20.0,
+            1.0,
+            0.0,
+            0.0,
+            InterpolationMode::Linear,
+        );
+        assert_eq!(output.len(), input.len());
+    }
+
+    #[test]
+    fn granular_without_jitter_reproduces_a_constant_signal() {
+        let input = vec![0.5f32; 4000];
+        let output = apply_granular(
+            &input,
+            16000.0,
+            1.0,
+            30.0,
+            20.0,
+            1.0,
+            0.0,
+            0.0,
+            InterpolationMode::Linear,
+        );
+        for (i, &s) in output.iter().enumerate() {
+            assert!((s - 0.5).abs() < 1e-2, "sample {i}: {s}");
+        }
+    }
+}
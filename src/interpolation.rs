@@ -0,0 +1,180 @@
+use std::f32::consts::PI;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+/// Number of phases in the precomputed polyphase windowed-sinc table.
+const FIR_PHASES: usize = 32;
+/// Number of taps per phase in the polyphase windowed-sinc table.
+const FIR_TAPS: usize = 8;
+
+/// Fractional-delay interpolation scheme used when reading a circular delay line
+/// at a non-integer position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InterpolationMode {
+    /// Rounds to the closest integer index. Cheapest, most aliasing.
+    Nearest,
+    /// Straight-line interpolation between the two surrounding samples.
+    Linear,
+    /// Raised-cosine interpolation between the two surrounding samples.
+    Cosine,
+    /// Catmull-Rom cubic interpolation over the four surrounding samples.
+    Cubic,
+    /// Windowed-sinc interpolation via a precomputed polyphase FIR table.
+    PolyphaseFir,
+}
+
+/// Reads `buf` (treated as a circular buffer) at fractional position `read_pos`,
+/// interpolating between neighbouring samples according to `mode`.
+pub fn interpolate(buf: &[f32], read_pos: f32, mode: InterpolationMode) -> f32 {
+    let len = buf.len();
+    if len == 0 {
+        return 0.0;
+    }
+
+    let idx0 = read_pos.floor();
+    let frac = read_pos - idx0;
+    let base = idx0 as isize;
+
+    match mode {
+        InterpolationMode::Nearest => {
+            let idx = wrap_index(base + frac.round() as isize, len);
+            buf[idx]
+        }
+        InterpolationMode::Linear => {
+            let a = buf[wrap_index(base, len)];
+            let b = buf[wrap_index(base + 1, len)];
+            a * (1.0 - frac) + b * frac
+        }
+        InterpolationMode::Cosine => {
+            let a = buf[wrap_index(base, len)];
+            let b = buf[wrap_index(base + 1, len)];
+            let f2 = (1.0 - (frac * PI).cos()) * 0.5;
+            a * (1.0 - f2) + b * f2
+        }
+        InterpolationMode::Cubic => {
+            let y0 = buf[wrap_index(base - 1, len)];
+            let y1 = buf[wrap_index(base, len)];
+            let y2 = buf[wrap_index(base + 1, len)];
+            let y3 = buf[wrap_index(base + 2, len)];
+            let a = -0.5 * y0 + 1.5 * y1 - 1.5 * y2 + 0.5 * y3;
+            let b = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+            let c = -0.5 * y0 + 0.5 * y2;
+            let d = y1;
+            ((a * frac + b) * frac + c) * frac + d
+        }
+        InterpolationMode::PolyphaseFir => {
+            let table = fir_table();
+            // A fraction that rounds up to a whole period selects phase 0 of the
+            // next integer index.
+            let phase_pos = (frac * FIR_PHASES as f32).round() as usize;
+            let base = base + (phase_pos / FIR_PHASES) as isize;
+            let taps = &table[phase_pos % FIR_PHASES];
+            let half = (FIR_TAPS / 2) as isize;
+            taps.iter()
+                .enumerate()
+                .map(|(i, &h)| {
+                    let offset = i as isize - half + 1;
+                    buf[wrap_index(base + offset, len)] * h
+                })
+                .sum()
+        }
+    }
+}
+
+fn wrap_index(idx: isize, len: usize) -> usize {
+    idx.rem_euclid(len as isize) as usize
+}
+
+fn fir_table() -> &'static Vec<Vec<f32>> {
+    static TABLE: OnceLock<Vec<Vec<f32>>> = OnceLock::new();
+    TABLE.get_or_init(build_fir_table)
+}
+
+/// Builds a `FIR_PHASES` x `FIR_TAPS` table of Hann-windowed sinc taps, one row
+/// per fractional phase, centered so that phase 0's only non-zero tap falls on the
+/// integer index. The Hann window scales that tap to about 0.95, so phase 0 passes
+/// the signal through at slightly reduced gain rather than exactly unmodified.
+fn build_fir_table() -> Vec<Vec<f32>> {
+    let half = (FIR_TAPS / 2) as isize;
+    (0..FIR_PHASES)
+        .map(|p| {
+            let frac = p as f32 / FIR_PHASES as f32;
+            (0..FIR_TAPS)
+                .map(|i| {
+                    let offset = i as isize - half + 1;
+                    sinc(offset as f32 - frac) * hann(i, FIR_TAPS)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn hann(i: usize, taps: usize) -> f32 {
+    0.5 - 0.5 * (2.0 * PI * i as f32 / (taps as f32 - 1.0)).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_picks_closest_sample() {
+        let buf = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(interpolate(&buf, 1.4, InterpolationMode::Nearest), 1.0);
+        assert_eq!(interpolate(&buf, 1.6, InterpolationMode::Nearest), 2.0);
+    }
+
+    #[test]
+    fn linear_interpolates_between_samples() {
+        let buf = [0.0, 1.0, 2.0, 3.0];
+        assert!((interpolate(&buf, 1.5, InterpolationMode::Linear) - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn integer_positions_reproduce_exact_samples() {
+        let buf = [0.2, -0.4, 0.6, -0.8, 1.0];
+        for mode in [
+            InterpolationMode::Nearest,
+            InterpolationMode::Linear,
+            InterpolationMode::Cosine,
+            InterpolationMode::Cubic,
+        ] {
+            let got = interpolate(&buf, 2.0, mode);
+            assert!(
+                (got - buf[2]).abs() < 1e-4,
+                "mode {:?} got {} expected {}",
+                mode,
+                got,
+                buf[2]
+            );
+        }
+    }
+
+    #[test]
+    fn polyphase_fir_stays_close_to_the_sample_it_centers_on() {
+        let buf = [0.2, -0.4, 0.6, -0.8, 1.0];
+        let got = interpolate(&buf, 2.0, InterpolationMode::PolyphaseFir);
+        assert!(
+            (got - buf[2]).abs() < 0.2,
+            "got {} expected near {}",
+            got,
+            buf[2]
+        );
+    }
+
+    #[test]
+    fn wraps_around_circular_buffer() {
+        let buf = [1.0, 2.0, 3.0];
+        assert_eq!(interpolate(&buf, -0.0, InterpolationMode::Nearest), 1.0);
+        assert!((interpolate(&buf, -1.0, InterpolationMode::Linear) - 3.0).abs() < 1e-6);
+    }
+}
@@ -1,14 +1,31 @@
+mod convert;
+mod convolution;
+mod effects;
+mod granular;
+mod interpolation;
+mod reverb;
+
 use anyhow::{Error, Result};
 use clap::Parser;
-use hound::{WavReader, WavWriter};
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+use effects::{ChorusProcessor, EchoProcessor, Effect, ReverbProcessor};
+use interpolation::InterpolationMode;
+
+/// Block size (in frames) used to drive a chain of `Effect` processors.
+/// Each processor only ever sees one block at a time, so the scratch buffers
+/// passed between chained stages stay fixed-size regardless of file length;
+/// the channel itself is still loaded and held in memory in full (see
+/// `process_file`), so this bounds per-stage working memory, not end-to-end
+/// memory use for the whole file.
+const STREAM_BLOCK_LEN: usize = 4096;
+
 #[derive(Parser)]
 #[command(
     name = "wav-files-echo",
-    about = "Add echo, reverb, or chorus effects to WAV files recursively"
+    about = "Add echo, reverb, chorus, convolution, granular, or freeze effects to WAV files recursively"
 )]
 struct Args {
     /// Input directory containing WAV files (processed recursively)
@@ -17,7 +34,8 @@ struct Args {
     /// Output directory for processed files (preserves relative structure)
     output_dir: PathBuf,
 
-    /// Effect type: echo, reverb, or chorus
+    /// Comma-separated chain of effects to apply in order: echo, reverb,
+    /// chorus, convolution, granular, freeze (e.g. "echo,reverb")
     #[arg(short, long, default_value = "echo")]
     effect: String,
 
@@ -40,6 +58,47 @@ struct Args {
     /// Chorus modulation depth in ms (ignored for echo/reverb)
     #[arg(long, default_value_t = 20.0f32)]
     chorus_depth_ms: f32,
+
+    /// Fractional-delay interpolation scheme used for delay-line reads
+    #[arg(long, value_enum, default_value_t = InterpolationMode::Linear)]
+    interpolation: InterpolationMode,
+
+    /// Freeverb damping coefficient (0.0-1.0); higher absorbs more high-frequency energy (reverb only)
+    #[arg(long, default_value_t = 0.5f32)]
+    damping: f32,
+
+    /// Impulse-response WAV file for the convolution effect
+    #[arg(long)]
+    ir_file: Option<PathBuf>,
+
+    /// Grain size in milliseconds (granular/freeze only)
+    #[arg(long, default_value_t = 50.0f32)]
+    grain_ms: f32,
+
+    /// Grain density in grains per second (granular only)
+    #[arg(long, default_value_t = 20.0f32)]
+    grain_density_hz: f32,
+
+    /// Grain playback rate; 1.0 is unshifted, 2.0 is an octave up (granular only)
+    #[arg(long, default_value_t = 1.0f32)]
+    grain_pitch: f32,
+
+    /// Random jitter applied to each grain's read position, in milliseconds (granular only)
+    #[arg(long, default_value_t = 0.0f32)]
+    grain_position_jitter_ms: f32,
+
+    /// Random jitter applied to each grain's pitch, as a fraction of `grain-pitch` (granular only)
+    #[arg(long, default_value_t = 0.0f32)]
+    grain_pitch_jitter: f32,
+
+    /// Position in the input to capture the sustained grain from, in milliseconds (freeze only)
+    #[arg(long, default_value_t = 0.0f32)]
+    freeze_position_ms: f32,
+
+    /// Force the output channel count, downmixing to mono or upmixing from a
+    /// mono source as needed (default: keep the input's channel layout)
+    #[arg(long)]
+    channels: Option<u16>,
 }
 
 fn main() -> Result<(), Error> {
@@ -64,225 +123,220 @@ fn main() -> Result<(), Error> {
 }
 
 /// Processes a single WAV file, applies the effect, and writes to output path.
+/// Any format hound can decode (8/16/24/32-bit int or 32-bit float, any
+/// channel count, any sample rate) is supported via the `convert` module;
+/// each channel is processed independently and reinterleaved on write.
 fn process_file(
     input_path: &Path,
     input_dir: &Path,
     output_dir: &Path,
     args: &Args,
 ) -> Result<(), Error> {
-    let mut reader = WavReader::open(input_path)?;
-    let spec = reader.spec();
+    let audio = convert::load(input_path)?;
+    let sr = audio.spec.sample_rate as f32;
 
-    if spec.channels != 1 {
-        return Err(Error::msg("Only mono audio supported"));
-    }
-    if spec.sample_rate != 16000 {
-        return Err(Error::msg("Only 16kHz sample rate supported"));
+    let channels = match args.channels {
+        Some(target) => convert::remix(audio.channels, target as usize)?,
+        None => audio.channels,
+    };
+
+    let effect_chain: Vec<&str> = args.effect.split(',').map(str::trim).collect();
+    let ir = load_resampled_ir(args, &effect_chain, sr)?;
+
+    let processed_channels: Vec<Vec<f32>> = channels
+        .iter()
+        .map(|channel| apply_effect_chain(channel, sr, args, &effect_chain, ir.as_deref()))
+        .collect::<Result<_, _>>()?;
+
+    let rel_path = input_path.strip_prefix(input_dir)?.to_path_buf();
+    let output_path = output_dir.join(rel_path);
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent)?;
     }
-    if spec.bits_per_sample != 16 {
-        return Err(Error::msg("Only 16-bit PCM supported"));
+
+    let mut spec = audio.spec;
+    spec.channels = processed_channels.len() as u16;
+    convert::store(&output_path, &processed_channels, spec)?;
+
+    Ok(())
+}
+
+/// Loads and downmixes the `--ir-file` impulse response for the convolution
+/// effect, resampling it to the input file's sample rate if they differ.
+/// Returns `None` if `effect_chain` doesn't use the convolution effect.
+fn load_resampled_ir(
+    args: &Args,
+    effect_chain: &[&str],
+    target_sr: f32,
+) -> Result<Option<Vec<f32>>, Error> {
+    if !effect_chain.contains(&"convolution") {
+        return Ok(None);
     }
 
-    let samples: Vec<i16> = reader.samples::<i16>().collect::<Result<Vec<_>, _>>()?;
+    let ir_path = args
+        .ir_file
+        .as_ref()
+        .ok_or_else(|| Error::msg("The convolution effect requires --ir-file"))?;
+    let ir_audio = convert::load(ir_path)?;
+    let ir_mono = convert::downmix_to_mono(&ir_audio.channels);
+    Ok(Some(convert::resample(
+        &ir_mono,
+        ir_audio.spec.sample_rate as f32,
+        target_sr,
+    )))
+}
 
-    let sr = spec.sample_rate as f32;
-    let samples_f32: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+/// Applies a chain of effects to a single channel's samples, one effect's
+/// output feeding the next's input.
+///
+/// When every stage in `effect_chain` is streamable (echo, reverb, chorus),
+/// the channel is processed in `STREAM_BLOCK_LEN`-frame blocks through
+/// stateful `Effect` processors, so only fixed-size scratch buffers are
+/// passed between chained stages rather than a full intermediate buffer per
+/// stage. The channel itself (and the file it came from) is still fully
+/// loaded beforehand, so this doesn't bound memory for arbitrarily large
+/// files end-to-end. Convolution, granular, and freeze aren't block-based
+/// (each needs the whole signal, or extends its length), so a chain
+/// containing any of them falls back to applying every stage over the full
+/// buffer at once.
+fn apply_effect_chain(
+    channel: &[f32],
+    sr: f32,
+    args: &Args,
+    effect_chain: &[&str],
+    ir: Option<&[f32]>,
+) -> Result<Vec<f32>, Error> {
+    if effect_chain
+        .iter()
+        .all(|name| matches!(*name, "echo" | "reverb" | "chorus"))
+    {
+        stream_chain(channel, sr, args, effect_chain)
+    } else {
+        whole_buffer_chain(channel, sr, args, effect_chain, ir)
+    }
+}
 
-    let processed_f32 = match args.effect.as_str() {
-        "echo" => apply_delay_effect(
-            &samples_f32,
+/// Builds a streaming processor for one of the block-based effects.
+fn build_processor(name: &str, sr: f32, args: &Args) -> Result<Box<dyn Effect>, Error> {
+    match name {
+        "echo" => Ok(Box::new(EchoProcessor::new(
             sr,
             args.wet,
             args.delay_ms as f32,
             args.decay_time_s,
-            false,
-        ),
-        "reverb" => apply_delay_effect(
-            &samples_f32,
+            args.interpolation,
+        ))),
+        "reverb" => Ok(Box::new(ReverbProcessor::new(
             sr,
             args.wet,
-            args.delay_ms as f32,
             args.decay_time_s,
-            true,
-        ),
-        "chorus" => apply_chorus_effect(
-            &samples_f32,
+            args.damping,
+        ))),
+        "chorus" => Ok(Box::new(ChorusProcessor::new(
             sr,
             args.wet,
             args.delay_ms as f32,
             args.decay_time_s,
             args.chorus_rate_hz,
             args.chorus_depth_ms,
-        ),
-        _ => return Err(Error::msg(format!("Unknown effect: {}", args.effect))),
-    };
-
-    let processed_samples: Vec<i16> = processed_f32
-        .into_iter()
-        .map(|s| (s * 32767.0).clamp(-32768.0, 32767.0) as i16)
-        .collect();
-
-    let rel_path = input_path.strip_prefix(input_dir)?.to_path_buf();
-    let output_path = output_dir.join(rel_path);
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
+            args.interpolation,
+        ))),
+        _ => Err(Error::msg(format!("Unknown streaming effect: {name}"))),
     }
-
-    let mut writer = WavWriter::create(output_path, spec)?;
-    for &sample in &processed_samples {
-        writer.write_sample(sample)?;
-    }
-    writer.finalize()?;
-
-    Ok(())
 }
 
-/// Applies a delay-based effect (echo or reverb) to mono f32 samples [-1.0, 1.0].
-/// Uses circular delay line with feedback computed from decay time (RT60 approximation).
-/// For reverb, applies a simple 1-pole lowpass filter in the feedback path.
-fn apply_delay_effect(
-    input: &[f32],
+/// Drives `channel` through a chain of streaming `Effect` processors in
+/// `STREAM_BLOCK_LEN`-frame blocks, ping-ponging between two scratch buffers
+/// so each processor reads one buffer and writes the other. `channel` and
+/// the returned output are both held in full; only the scratch buffers
+/// passed between chain stages are block-sized.
+fn stream_chain(
+    channel: &[f32],
     sr: f32,
-    wet: f32,
-    delay_ms: f32,
-    decay_time_s: f32,
-    lowpass: bool,
-) -> Vec<f32> {
-    let delay_samples = (delay_ms * sr / 1000.0).max(1.0) as usize;
-    let delay_s = delay_ms / 1000.0;
-    let feedback = 10f32.powf(-3.0 * delay_s / decay_time_s).clamp(0.0, 1.0);
-    let dry = 1.0 - wet;
-
-    let mut delay_line = vec![0.0f32; delay_samples];
-    let mut output = vec![0.0f32; input.len()];
-    let mut write_pos = 0usize;
-    let mut prev_lp = 0.0f32;
-    let lp_coeff = 0.5f32; // Simple lowpass coefficient
-
-    for (i, &inp) in input.iter().enumerate() {
-        let read_pos = ((write_pos as isize - delay_samples as isize)
-            .rem_euclid(delay_samples as isize)) as usize;
-        let delayed = delay_line[read_pos];
-
-        output[i] = dry * inp + wet * delayed;
-
-        let mut feedback_val = delayed;
-        if lowpass {
-            let lp_out = lp_coeff * feedback_val + (1.0 - lp_coeff) * prev_lp;
-            feedback_val = lp_out;
-            prev_lp = lp_out;
+    args: &Args,
+    effect_chain: &[&str],
+) -> Result<Vec<f32>, Error> {
+    let mut chain: Vec<Box<dyn Effect>> = effect_chain
+        .iter()
+        .map(|&name| build_processor(name, sr, args))
+        .collect::<Result<_, _>>()?;
+
+    let mut output = vec![0.0f32; channel.len()];
+    let mut buf_a = vec![0.0f32; STREAM_BLOCK_LEN];
+    let mut buf_b = vec![0.0f32; STREAM_BLOCK_LEN];
+
+    for chunk_start in (0..channel.len()).step_by(STREAM_BLOCK_LEN) {
+        let chunk_end = (chunk_start + STREAM_BLOCK_LEN).min(channel.len());
+        let len = chunk_end - chunk_start;
+        buf_a[..len].copy_from_slice(&channel[chunk_start..chunk_end]);
+
+        let mut input_is_a = true;
+        for processor in chain.iter_mut() {
+            if input_is_a {
+                processor.process_block(&buf_a[..len], &mut buf_b[..len]);
+            } else {
+                processor.process_block(&buf_b[..len], &mut buf_a[..len]);
+            }
+            input_is_a = !input_is_a;
         }
 
-        delay_line[write_pos] = inp + feedback * feedback_val;
-        write_pos = (write_pos + 1) % delay_samples;
-    }
-
-    output
-}
-
-/// Applies a simple chorus effect using modulated delay.
-fn apply_chorus_effect(
-    input: &[f32],
-    sr: f32,
-    wet: f32,
-    delay_ms: f32,
-    decay_time_s: f32,
-    rate_hz: f32,
-    depth_ms: f32,
-) -> Vec<f32> {
-    let base_delay_samples = (delay_ms * sr / 1000.0).max(1.0);
-    let depth_samples = (depth_ms * sr / 1000.0).max(1.0);
-    let delay_s = delay_ms / 1000.0;
-    let feedback = 10f32.powf(-3.0 * delay_s / decay_time_s).clamp(0.0, 0.3); // Low feedback for chorus
-    let dry = 1.0 - wet;
-    let buffer_size = (base_delay_samples + depth_samples * 2.0) as usize; // Extra space for modulation
-
-    let mut delay_line = vec![0.0f32; buffer_size];
-    let mut output = vec![0.0f32; input.len()];
-    let mut write_pos = 0usize;
-    let mut phase = 0.0f32;
-    let phase_inc = 2.0 * std::f32::consts::PI * rate_hz / sr;
-
-    for (i, &inp) in input.iter().enumerate() {
-        let modulation = (phase.sin() + 1.0) * 0.5; // 0.0 to 1.0
-        let curr_delay = base_delay_samples + modulation * depth_samples;
-        let read_pos_float = (write_pos as f32 - curr_delay) % (buffer_size as f32);
-        let read_pos = read_pos_float.max(0.0) as usize % buffer_size;
-
-        // Simple linear interpolation for fractional delay
-        let delayed = if read_pos_float.fract() == 0.0 {
-            delay_line[read_pos]
+        let result = if input_is_a {
+            &buf_a[..len]
         } else {
-            let pos1 = read_pos;
-            let pos2 = (pos1 + 1) % buffer_size;
-            let frac = read_pos_float.fract();
-            delay_line[pos1] * (1.0 - frac) + delay_line[pos2] * frac
+            &buf_b[..len]
         };
-
-        output[i] = dry * inp + wet * delayed;
-
-        let feedback_val = feedback * delayed;
-        delay_line[write_pos] = inp + feedback_val;
-        write_pos = (write_pos + 1) % buffer_size;
-
-        phase += phase_inc;
-        if phase >= 2.0 * std::f32::consts::PI {
-            phase -= 2.0 * std::f32::consts::PI;
-        }
+        output[chunk_start..chunk_end].copy_from_slice(result);
     }
 
-    output
+    Ok(output)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_echo_basic() {
-        let sr = 16000.0;
-        // Impulse at ~0.625s (10000 samples / 16000 Hz)
-        let input: Vec<f32> = std::iter::repeat(0.0)
-            .take(10000)
-            .chain(std::iter::once(1.0))
-            .chain(std::iter::repeat(0.0).take(4000))
-            .collect();
-        let output = apply_delay_effect(&input, sr, 0.5, 250.0, 1.0, false);
-        // Check for echo at ~250ms, amplitude ~0.5 * feedback
-        let impulse_idx = 10000;
-        let delay_idx = impulse_idx + (250.0 * sr / 1000.0) as usize;
-        // The delayed signal is `dry * inp + wet * delayed`.
-        // At `delay_idx`, `inp` is 0. The `delayed` value is the impulse from `impulse_idx`.
-        // The value at `impulse_idx` in the delay line is `inp + feedback * feedback_val`.
-        // At `impulse_idx`, `inp` is 1.0, `feedback_val` is 0. So `delay_line[write_pos]` becomes 1.0.
-        // So `output[delay_idx]` should be `wet * 1.0` = 0.5.
-        assert!(
-            (output[delay_idx] - 0.5).abs() < 0.001,
-            "Echo amplitude is incorrect. Got {}",
-            output[delay_idx]
-        );
-    }
-
-    #[test]
-    fn test_reverb_lowpass() {
-        let sr = 16000.0;
-        let input: Vec<f32> = vec![1.0];
-        let output = apply_delay_effect(&input, sr, 1.0, 10.0, 0.1, true); // Short delay, quick decay, full wet
-        // With lowpass, feedback should decay faster in high freq, but hard to test simply
-        // Basic check: output not empty
-        assert_eq!(output.len(), 1);
-        // Note: More comprehensive tests would require longer signals
+/// Applies every stage in `effect_chain` over the whole channel at once,
+/// feeding each stage's output into the next. Used whenever the chain
+/// contains convolution, granular, or freeze, none of which fits the
+/// fixed-size block model of `stream_chain`.
+fn whole_buffer_chain(
+    channel: &[f32],
+    sr: f32,
+    args: &Args,
+    effect_chain: &[&str],
+    ir: Option<&[f32]>,
+) -> Result<Vec<f32>, Error> {
+    let mut signal = channel.to_vec();
+
+    for &name in effect_chain {
+        signal = match name {
+            "echo" | "reverb" | "chorus" => {
+                let mut processor = build_processor(name, sr, args)?;
+                let mut out = vec![0.0f32; signal.len()];
+                processor.process_block(&signal, &mut out);
+                out
+            }
+            "convolution" => {
+                let ir = ir.expect("ir is pre-loaded for the convolution effect");
+                convolution::convolve(&signal, ir, args.wet)
+            }
+            "granular" => granular::apply_granular(
+                &signal,
+                sr,
+                args.wet,
+                args.grain_ms,
+                args.grain_density_hz,
+                args.grain_pitch,
+                args.grain_position_jitter_ms,
+                args.grain_pitch_jitter,
+                args.interpolation,
+            ),
+            "freeze" => granular::apply_freeze(
+                &signal,
+                sr,
+                args.wet,
+                args.freeze_position_ms,
+                args.grain_ms,
+            ),
+            _ => return Err(Error::msg(format!("Unknown effect: {name}"))),
+        };
     }
 
-    #[test]
-    fn test_chorus_modulation() {
-        let sr = 16000.0;
-        let input: Vec<f32> = vec![1.0; 1000];
-        let output = apply_chorus_effect(&input, sr, 0.5, 10.0, 1.0, 1.0, 5.0);
-        // Check variance in output due to modulation
-        let mean: f32 = output.iter().sum::<f32>() / output.len() as f32;
-        let variance: f32 =
-            output.iter().map(|&x| (x - mean).powi(2)).sum::<f32>() / output.len() as f32;
-        assert!(variance > 0.001); // Some variation from dry signal
-    }
+    Ok(signal)
 }
@@ -0,0 +1,105 @@
+//! Freeverb-style reverberator: 8 parallel lowpass-comb filters feeding 4
+//! series allpass filters. `Comb` and `Allpass` hold their own delay-line
+//! state so `effects::ReverbProcessor` can drive them block-by-block across
+//! a stream; `build_network` sizes both filter banks for a sample rate,
+//! decay time, and damping amount.
+
+/// Reference comb delay lengths (in samples) tuned at a 44.1kHz sample rate.
+const COMB_DELAYS: [usize; 8] = [1557, 1617, 1491, 1422, 1277, 1356, 1188, 1116];
+/// Reference allpass delay lengths (in samples) tuned at a 44.1kHz sample rate.
+const ALLPASS_DELAYS: [usize; 4] = [225, 556, 441, 341];
+/// Fixed feedback gain used by every allpass filter in the chain.
+const ALLPASS_GAIN: f32 = 0.5;
+
+/// A lowpass-damped feedback comb filter: one of the eight parallel resonators
+/// that give Freeverb its dense early-reflection texture.
+pub(crate) struct Comb {
+    buffer: Vec<f32>,
+    pos: usize,
+    filter_store: f32,
+    feedback: f32,
+    damp: f32,
+}
+
+impl Comb {
+    fn new(delay: usize, feedback: f32, damp: f32) -> Self {
+        Self {
+            buffer: vec![0.0; delay.max(1)],
+            pos: 0,
+            filter_store: 0.0,
+            feedback,
+            damp,
+        }
+    }
+
+    pub(crate) fn process(&mut self, input: f32) -> f32 {
+        let out = self.buffer[self.pos];
+        self.filter_store = out * (1.0 - self.damp) + self.filter_store * self.damp;
+        self.buffer[self.pos] = input + self.filter_store * self.feedback;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        out
+    }
+
+    /// Clears the delay line and lowpass memory back to silence.
+    pub(crate) fn reset(&mut self) {
+        self.buffer.iter_mut().for_each(|s| *s = 0.0);
+        self.pos = 0;
+        self.filter_store = 0.0;
+    }
+}
+
+/// Freeverb's allpass-style diffuser, used to smear the comb output into a
+/// smoother decay. As in Freeverb, it is not a true allpass: with a gain of 0.5
+/// its magnitude response ripples between 1 and 5/3 across frequency.
+pub(crate) struct Allpass {
+    buffer: Vec<f32>,
+    pos: usize,
+    gain: f32,
+}
+
+impl Allpass {
+    fn new(delay: usize, gain: f32) -> Self {
+        Self {
+            buffer: vec![0.0; delay.max(1)],
+            pos: 0,
+            gain,
+        }
+    }
+
+    pub(crate) fn process(&mut self, input: f32) -> f32 {
+        let buffered = self.buffer[self.pos];
+        let out = -input + buffered;
+        self.buffer[self.pos] = input + buffered * self.gain;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        out
+    }
+
+    /// Clears the delay line back to silence.
+    pub(crate) fn reset(&mut self) {
+        self.buffer.iter_mut().for_each(|s| *s = 0.0);
+        self.pos = 0;
+    }
+}
+
+/// Builds the 8 combs and 4 allpasses of a Freeverb network, scaling the
+/// reference (44.1kHz) delay lengths to `sr`.
+///
+/// `decay_time_s` maps to the combs' feedback gain (clamped to 0.7-0.98, the
+/// range Freeverb's room-size parameter covers), and `damping` (0.0-1.0) sets
+/// the one-pole lowpass cutoff in each comb's feedback path.
+pub(crate) fn build_network(sr: f32, decay_time_s: f32, damping: f32) -> (Vec<Comb>, Vec<Allpass>) {
+    let scale = sr / 44100.0;
+    let feedback = (0.7 + 0.28 * (decay_time_s / (decay_time_s + 1.0))).clamp(0.7, 0.98);
+    let damp = damping.clamp(0.0, 1.0);
+
+    let combs = COMB_DELAYS
+        .iter()
+        .map(|&delay| Comb::new(((delay as f32) * scale).round() as usize, feedback, damp))
+        .collect();
+    let allpasses = ALLPASS_DELAYS
+        .iter()
+        .map(|&delay| Allpass::new(((delay as f32) * scale).round() as usize, ALLPASS_GAIN))
+        .collect();
+
+    (combs, allpasses)
+}